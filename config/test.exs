@@ -6,7 +6,8 @@ config :ethproofs_client,
   elf_path: "/tmp/test.elf",
   ethproofs_rpc_url: nil,
   ethproofs_api_key: nil,
-  ethproofs_cluster_id: nil
+  ethproofs_cluster_id: nil,
+  input_dir: nil
 
 # Phoenix test configuration
 config :ethproofs_client, EthProofsClientWeb.Endpoint,
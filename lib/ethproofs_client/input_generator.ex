@@ -53,6 +53,21 @@ defmodule EthProofsClient.InputGenerator do
     GenServer.call(__MODULE__, :status)
   end
 
+  @doc """
+  Dump a generated input file as human-readable JSON next to it (`<block>.json`),
+  returning the path of the JSON file. Useful for debugging failed proofs.
+  """
+  def dump_input_json(_input_path), do: :erlang.nif_error(:nif_not_loaded)
+
+  @doc """
+  Check that a previously generated input file deserializes into a valid input.
+
+  Returns `{:ok, true}` for a valid file, or `{:error, reason}` describing why it
+  can't be read (e.g. a truncated or corrupted file). Useful to verify persisted
+  inputs before re-submitting them after a restart.
+  """
+  def load_input(_input_path), do: :erlang.nif_error(:nif_not_loaded)
+
   # --- Callbacks ---
 
   @impl true
@@ -259,7 +274,16 @@ defmodule EthProofsClient.InputGenerator do
            EthProofsClient.EthRpc.get_block_by_number(block_number, true, raw: true),
          {:ok, witness_json_bytes} <-
            EthProofsClient.EthRpc.debug_execution_witness(block_number, raw: true),
-         {:ok, input_path} <- generate_input(block_json_bytes, witness_json_bytes) do
+         {:ok,
+          %{
+            path: input_path,
+            digest: digest,
+            stats: stats,
+            validation_error: validation_error
+          }} <-
+           generate_input(block_json_bytes, witness_json_bytes, input_dir()) do
+      log_input_info(block_number, digest, stats)
+      maybe_log_validation_error(block_number, validation_error)
       {:ok, input_path}
     else
       {:error, reason} -> {:error, reason}
@@ -267,6 +291,23 @@ defmodule EthProofsClient.InputGenerator do
     end
   end
 
+  # The digest is only logged, so inputs generated for the same block can be
+  # compared across runs.
+  defp log_input_info(block_number, digest, stats) do
+    Logger.info(
+      "Input for block #{block_number} is #{stats.serialized_bytes} bytes " <>
+        "(#{stats.blocks} blocks, #{stats.accounts} accounts, " <>
+        "#{stats.storage_slots} storage slots), digest #{digest}"
+    )
+  end
+
+  # The input is still proved, the check only flags witnesses likely to fail.
+  defp maybe_log_validation_error(_block_number, nil), do: :ok
+
+  defp maybe_log_validation_error(block_number, validation_error) do
+    Logger.warning("Input for block #{block_number} may be incomplete: #{validation_error}")
+  end
+
   defp handle_new_block(block_number, block_timestamp, state) do
     # Always compute and store block info for the dashboard
     blocks_remaining = 100 - rem(block_number, 100)
@@ -313,7 +354,7 @@ defmodule EthProofsClient.InputGenerator do
         Logger.debug("Block #{block_number} already generating, skipping")
         state
 
-      File.exists?(Integer.to_string(block_number) <> ".bin") ->
+      File.exists?(Path.join(input_dir(), Integer.to_string(block_number) <> ".bin")) ->
         Logger.debug("Block #{block_number} input file exists, skipping")
         state
 
@@ -324,6 +365,12 @@ defmodule EthProofsClient.InputGenerator do
     end
   end
 
+  # Directory the NIF writes `.bin` inputs to, defaulting to the directory the
+  # application was started from (the project root under `mix`).
+  defp input_dir do
+    Application.get_env(:ethproofs_client, :input_dir) || File.cwd!()
+  end
+
   defp schedule_fetch do
     Process.send_after(self(), :fetch_latest_block_number, @block_fetch_interval)
   end
@@ -379,6 +426,6 @@ defmodule EthProofsClient.InputGenerator do
   end
 
   # NIF stub - replaced at runtime by Rustler
-  defp generate_input(_rpc_block_bytes, _rpc_execution_witness_bytes),
+  defp generate_input(_rpc_block_bytes, _rpc_execution_witness_bytes, _output_dir),
     do: :erlang.nif_error(:nif_not_loaded)
 end
@@ -0,0 +1,58 @@
+use std::{io, path::PathBuf};
+
+use ethrex_common::{Address, H256};
+use ethrex_rlp::error::RLPDecodeError;
+
+/// Failures of the input generation pipeline, one variant per stage. Messages
+/// already include the underlying error, since the NIF only returns the message.
+#[derive(Debug, thiserror::Error)]
+pub enum InputGeneratorError {
+    #[error("Failed to get genesis config for Mainnet: {0}")]
+    Genesis(String),
+    #[error("Failed to deserialize RPC block: {0}")]
+    RpcBlockDeserialization(serde_json::Error),
+    #[error("Failed to deserialize RPC execution witness: {0}")]
+    RpcExecutionWitnessDeserialization(serde_json::Error),
+    #[error("Failed to decode execution witness headers: {0}")]
+    ExecutionWitnessHeaders(RLPDecodeError),
+    #[error("Execution witness contains no block headers")]
+    EmptyExecutionWitness,
+    #[error("Execution witness parent header is block {witness_parent}, but RPC block is {block}")]
+    BlockNumberMismatch { witness_parent: u64, block: u64 },
+    #[error(
+        "Execution witness parent hash {witness:#x} does not match RPC block parent hash {block:#x}"
+    )]
+    ParentHashMismatch { witness: H256, block: H256 },
+    #[error("Failed to convert RPC block to internal block: {0}")]
+    BlockConversion(String),
+    #[error("Failed to create execution witness from RPC data: {0}")]
+    ExecutionWitness(String),
+    #[error("Failed to get output directory")]
+    OutputDir,
+    #[error("Output directory {} does not exist", .0.display())]
+    OutputDirNotFound(PathBuf),
+    #[error("Output path {} is not a directory", .0.display())]
+    OutputDirNotADirectory(PathBuf),
+    #[error("Output directory {} is not writable", .0.display())]
+    OutputDirNotWritable(PathBuf),
+    #[error("Failed to access output directory {}: {error}", .path.display())]
+    OutputDirAccess { path: PathBuf, error: io::Error },
+    #[error("Failed to serialize input to bytes: {0}")]
+    Serialization(rkyv::rancor::Error),
+    #[error("Failed to write input file: {0}")]
+    Write(io::Error),
+}
+
+/// Ways in which a `ProgramInput` fails to be self-contained.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum InputValidationError {
+    #[error("Failed to recover sender of transaction {tx_hash:#x}: {reason}")]
+    Sender { tx_hash: H256, reason: String },
+    #[error(
+        "Execution witness is missing accounts {accounts:?} and storage slots {storage_slots:?}"
+    )]
+    MissingKeys {
+        accounts: Vec<Address>,
+        storage_slots: Vec<H256>,
+    },
+}
@@ -1,29 +1,350 @@
-use std::path::Path;
+use std::{
+    collections::BTreeSet,
+    io,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
 
+use ethrex_common::{
+    Address, H160, H256,
+    types::{BlockHeader, TxKind},
+};
 use ethrex_config::networks::{Network, PublicNetwork};
-use ethrex_guest::input::ProgramInput;
+use ethrex_guest::input::{ArchivedProgramInput, ProgramInput};
+use ethrex_rlp::decode::RLPDecode;
 use ethrex_rpc::{
     debug::execution_witness::{RpcExecutionWitness, execution_witness_from_rpc_chain_config},
     types::block::RpcBlock,
 };
+use sha3::{Digest, Keccak256};
+
+mod error;
+
+pub use error::{InputGeneratorError, InputValidationError};
 
 const CARGO_MANIFEST_DIR: &str = env!("CARGO_MANIFEST_DIR");
 
 const MAINNET_ELASTICITY_MULTIPLIER: u64 = 2;
 
-#[rustler::nif]
-fn generate_input(rpc_block: String, rpc_execution_witness: String) -> Result<String, String> {
+/// Serializes `input` with rkyv and writes the resulting bytes to `path`.
+///
+/// The bytes are first written to a uniquely named temporary file next to `path`
+/// and then renamed into place, so readers never observe a partially written file.
+pub fn write_program_input(input: &ProgramInput, path: &Path) -> io::Result<()> {
+    let input_bytes = rkyv::to_bytes::<rkyv::rancor::Error>(input).map_err(io::Error::other)?;
+
+    write_input_bytes(&input_bytes, path)
+}
+
+/// Atomically writes already serialized input bytes to `path`, see
+/// [`write_program_input`].
+fn write_input_bytes(input_bytes: &[u8], path: &Path) -> io::Result<()> {
+    let tmp_path = path.with_extension(format!("{}.bin.tmp", unique_suffix()?));
+
+    std::fs::write(&tmp_path, input_bytes)
+        .and_then(|()| std::fs::rename(&tmp_path, path))
+        .inspect_err(|_| {
+            let _ = std::fs::remove_file(&tmp_path);
+        })
+}
+
+/// `{pid}.{nanos}`, for temporary file names that don't collide across processes
+/// or retries.
+fn unique_suffix() -> io::Result<String> {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(io::Error::other)?
+        .as_nanos();
+
+    Ok(format!("{}.{}", std::process::id(), nanos))
+}
+
+/// Reads back a `ProgramInput` previously written with [`write_program_input`].
+pub fn read_program_input(path: &Path) -> io::Result<ProgramInput> {
+    let input_bytes = std::fs::read(path)?;
+
+    rkyv::from_bytes::<ProgramInput, rkyv::rancor::Error>(&input_bytes)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+}
+
+/// Checks that `path` holds a valid serialized `ProgramInput` by validating its
+/// archived form, without deserializing it.
+pub fn check_program_input_file(path: &Path) -> io::Result<()> {
+    let input_bytes = std::fs::read(path)?;
+
+    rkyv::access::<ArchivedProgramInput, rkyv::rancor::Error>(&input_bytes)
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+
+    Ok(())
+}
+
+/// Renders `input` as pretty-printed JSON, for inspecting and diffing inputs.
+pub fn program_input_to_json(input: &ProgramInput) -> Result<String, serde_json::Error> {
+    serde_json::to_string_pretty(input)
+}
+
+/// Keccak-256 of the rkyv serialization of `input`, i.e. of the contents of the
+/// file [`write_program_input`] produces. Identical inputs yield identical digests.
+pub fn program_input_digest(input: &ProgramInput) -> Result<[u8; 32], rkyv::rancor::Error> {
+    let input_bytes = rkyv::to_bytes::<rkyv::rancor::Error>(input)?;
+
+    Ok(input_bytes_digest(&input_bytes))
+}
+
+fn input_bytes_digest(input_bytes: &[u8]) -> [u8; 32] {
+    Keccak256::digest(input_bytes).into()
+}
+
+/// Size figures of a generated `ProgramInput`, useful for scheduling provers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, rustler::NifMap)]
+pub struct ProgramInputStats {
+    /// Length of the rkyv serialization, i.e. of the `.bin` file.
+    pub serialized_bytes: usize,
+    pub blocks: usize,
+    /// Account addresses among the execution witness keys.
+    pub accounts: usize,
+    /// Storage slots among the execution witness keys.
+    pub storage_slots: usize,
+}
+
+/// Computes the [`ProgramInputStats`] of `input`.
+pub fn program_input_stats(input: &ProgramInput) -> Result<ProgramInputStats, rkyv::rancor::Error> {
+    let input_bytes = rkyv::to_bytes::<rkyv::rancor::Error>(input)?;
+
+    Ok(input_stats(input, input_bytes.len()))
+}
+
+fn input_stats(input: &ProgramInput, serialized_bytes: usize) -> ProgramInputStats {
+    // Witness keys are the preimages of the hashed trie paths: 20-byte account
+    // addresses and 32-byte storage slots.
+    let keys = &input.execution_witness.keys;
+
+    ProgramInputStats {
+        serialized_bytes,
+        blocks: input.blocks.len(),
+        accounts: keys.iter().filter(|key| key.len() == 20).count(),
+        storage_slots: keys.iter().filter(|key| key.len() == 32).count(),
+    }
+}
+
+/// EIP-4788 beacon roots system contract.
+const BEACON_ROOTS_ADDRESS: Address = H160([
+    0x00, 0x0f, 0x3d, 0xf6, 0xd7, 0x32, 0x80, 0x7e, 0xf1, 0x31, 0x9f, 0xb7, 0xb8, 0xbb, 0x85, 0x22,
+    0xd0, 0xbe, 0xac, 0x02,
+]);
+
+/// EIP-2935 block hash history system contract.
+const HISTORY_STORAGE_ADDRESS: Address = H160([
+    0x00, 0x00, 0xf9, 0x08, 0x27, 0xf1, 0xc5, 0x3a, 0x10, 0xcb, 0x7a, 0x02, 0x33, 0x5b, 0x17, 0x53,
+    0x20, 0x00, 0x29, 0x35,
+]);
+
+/// Ring buffer length of both the beacon roots and the history system contracts.
+const SYSTEM_CONTRACT_BUFFER_LENGTH: u64 = 8191;
+
+/// Checks that the execution witness of `input` covers the state its blocks are
+/// known to touch without executing them, returning the keys missing from the
+/// witness. Accounts: coinbase (only paid fees when the block has transactions),
+/// transaction senders, call targets, withdrawal recipients and the system
+/// contracts. Storage: the slots written by the EIP-4788 and EIP-2935 system
+/// calls. Slots only reached through contract execution can't be derived from
+/// the block and aren't checked.
+///
+/// Witness keys are bare preimages with nothing tying a slot to its account, so
+/// a slot counts as present if any account's storage includes it.
+pub fn validate_program_input(input: &ProgramInput) -> Result<(), InputValidationError> {
+    let witness_keys: BTreeSet<&[u8]> = input
+        .execution_witness
+        .keys
+        .iter()
+        .map(|key| key.as_slice())
+        .collect();
+
+    let mut touched_accounts = BTreeSet::<Address>::new();
+    let mut touched_storage_slots = BTreeSet::<H256>::new();
+
+    for block in &input.blocks {
+        let header = &block.header;
+
+        if !block.body.transactions.is_empty() {
+            touched_accounts.insert(header.coinbase);
+        }
+
+        if header.parent_beacon_block_root.is_some() {
+            let slot = header.timestamp % SYSTEM_CONTRACT_BUFFER_LENGTH;
+
+            touched_accounts.insert(BEACON_ROOTS_ADDRESS);
+            touched_storage_slots.insert(H256::from_low_u64_be(slot));
+            touched_storage_slots
+                .insert(H256::from_low_u64_be(slot + SYSTEM_CONTRACT_BUFFER_LENGTH));
+        }
+
+        // Prague blocks, the first to carry a requests hash, also store the parent
+        // hash in the history contract.
+        if header.requests_hash.is_some() && header.number > 0 {
+            touched_accounts.insert(HISTORY_STORAGE_ADDRESS);
+            touched_storage_slots.insert(H256::from_low_u64_be(
+                (header.number - 1) % SYSTEM_CONTRACT_BUFFER_LENGTH,
+            ));
+        }
+
+        for tx in &block.body.transactions {
+            let sender = tx.sender().map_err(|e| InputValidationError::Sender {
+                tx_hash: tx.hash(),
+                reason: e.to_string(),
+            })?;
+
+            touched_accounts.insert(sender);
+
+            if let TxKind::Call(to) = tx.to() {
+                touched_accounts.insert(to);
+            }
+        }
+
+        for withdrawal in block.body.withdrawals.iter().flatten() {
+            if withdrawal.amount > 0 {
+                touched_accounts.insert(withdrawal.address);
+            }
+        }
+    }
+
+    let accounts: Vec<Address> = touched_accounts
+        .into_iter()
+        .filter(|address| !witness_keys.contains(address.as_bytes()))
+        .collect();
+
+    let storage_slots: Vec<H256> = touched_storage_slots
+        .into_iter()
+        .filter(|slot| !witness_keys.contains(slot.as_bytes()))
+        .collect();
+
+    if !accounts.is_empty() || !storage_slots.is_empty() {
+        return Err(InputValidationError::MissingKeys {
+            accounts,
+            storage_slots,
+        });
+    }
+
+    Ok(())
+}
+
+/// Checks that `rpc_execution_witness` was taken for `rpc_block` by looking for the
+/// block's parent among the witness headers, so a mismatched witness fails here
+/// instead of deep inside the guest.
+fn validate_execution_witness(
+    rpc_block: &RpcBlock,
+    rpc_execution_witness: &RpcExecutionWitness,
+) -> Result<(), InputGeneratorError> {
+    let block_number = rpc_block.header.number;
+
+    let parent_header = rpc_execution_witness
+        .headers
+        .iter()
+        .map(|header| BlockHeader::decode(header))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(InputGeneratorError::ExecutionWitnessHeaders)?
+        .into_iter()
+        .max_by_key(|header| header.number)
+        .ok_or(InputGeneratorError::EmptyExecutionWitness)?;
+
+    if block_number.checked_sub(1) != Some(parent_header.number) {
+        return Err(InputGeneratorError::BlockNumberMismatch {
+            witness_parent: parent_header.number,
+            block: block_number,
+        });
+    }
+
+    let parent_hash = parent_header.hash();
+
+    if parent_hash != rpc_block.header.parent_hash {
+        return Err(InputGeneratorError::ParentHashMismatch {
+            witness: parent_hash,
+            block: rpc_block.header.parent_hash,
+        });
+    }
+
+    Ok(())
+}
+
+/// A `ProgramInput` written to disk by [`generate_input_inner`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GeneratedInput {
+    pub path: PathBuf,
+    /// Same value [`program_input_digest`] returns for the written input.
+    pub digest: [u8; 32],
+    pub stats: ProgramInputStats,
+    /// Why [`validate_program_input`] considers the input incomplete, if it does.
+    pub validation_error: Option<InputValidationError>,
+}
+
+/// Resolves the directory generated inputs are written to, defaulting to the root
+/// of the Elixir project, and checks that it exists and is writable.
+fn resolve_output_dir(output_dir: Option<&Path>) -> Result<PathBuf, InputGeneratorError> {
+    let output_dir = match output_dir {
+        Some(output_dir) => output_dir.to_path_buf(),
+        None => Path::new(CARGO_MANIFEST_DIR)
+            .parent()
+            .and_then(Path::parent)
+            .ok_or(InputGeneratorError::OutputDir)?
+            .to_path_buf(),
+    };
+
+    let metadata =
+        std::fs::metadata(&output_dir).map_err(|e| output_dir_error(output_dir.clone(), e))?;
+
+    if !metadata.is_dir() {
+        return Err(InputGeneratorError::OutputDirNotADirectory(output_dir));
+    }
+
+    // Permission bits don't tell whether this process may write there (ownership,
+    // read-only mounts), so probe by actually creating a file.
+    let probe_path = output_dir.join(format!(
+        ".write_probe.{}",
+        unique_suffix().map_err(|e| output_dir_error(output_dir.clone(), e))?
+    ));
+
+    std::fs::File::create_new(&probe_path).map_err(|e| output_dir_error(output_dir.clone(), e))?;
+    std::fs::remove_file(&probe_path).map_err(|e| output_dir_error(output_dir.clone(), e))?;
+
+    Ok(output_dir)
+}
+
+fn output_dir_error(output_dir: PathBuf, error: io::Error) -> InputGeneratorError {
+    match error.kind() {
+        io::ErrorKind::NotFound => InputGeneratorError::OutputDirNotFound(output_dir),
+        io::ErrorKind::PermissionDenied | io::ErrorKind::ReadOnlyFilesystem => {
+            InputGeneratorError::OutputDirNotWritable(output_dir)
+        }
+        _ => InputGeneratorError::OutputDirAccess {
+            path: output_dir,
+            error,
+        },
+    }
+}
+
+/// Builds the `ProgramInput` for the given JSON-encoded RPC block and execution
+/// witness and writes it to `output_dir` (the root of the Elixir project when
+/// `None`), returning the path of the written file along with its digest, stats
+/// and validation result.
+pub fn generate_input_inner(
+    rpc_block: &str,
+    rpc_execution_witness: &str,
+    output_dir: Option<&Path>,
+) -> Result<GeneratedInput, InputGeneratorError> {
+    let output_dir = resolve_output_dir(output_dir)?;
+
     let chain_config = Network::PublicNetwork(PublicNetwork::Mainnet)
         .get_genesis()
-        .map_err(|e| format!("Failed to get genesis config for Mainnet: {}", e))?
+        .map_err(|e| InputGeneratorError::Genesis(e.to_string()))?
         .config;
 
-    let rpc_block: RpcBlock = serde_json::from_str(&rpc_block)
-        .map_err(|e| format!("Failed to deserialize RPC block: {}", e))?;
+    let rpc_block: RpcBlock =
+        serde_json::from_str(rpc_block).map_err(InputGeneratorError::RpcBlockDeserialization)?;
+
+    let rpc_execution_witness: RpcExecutionWitness = serde_json::from_str(rpc_execution_witness)
+        .map_err(InputGeneratorError::RpcExecutionWitnessDeserialization)?;
 
-    let rpc_execution_witness: RpcExecutionWitness =
-        serde_json::from_str(&rpc_execution_witness)
-            .map_err(|e| format!("Failed to deserialize RPC execution witness: {}", e))?;
+    validate_execution_witness(&rpc_block, &rpc_execution_witness)?;
 
     let block_number = rpc_block.header.number;
 
@@ -31,35 +352,675 @@ fn generate_input(rpc_block: String, rpc_execution_witness: String) -> Result<St
         blocks: vec![
             rpc_block
                 .try_into()
-                .map_err(|e| format!("Failed to convert RPC block to internal block: {}", e))?,
+                .map_err(|e| InputGeneratorError::BlockConversion(e.to_string()))?,
         ],
         execution_witness: execution_witness_from_rpc_chain_config(
             rpc_execution_witness,
             chain_config,
             block_number,
         )
-        .map_err(|e| format!("Failed to create execution witness from RPC data: {}", e))?,
+        .map_err(|e| InputGeneratorError::ExecutionWitness(e.to_string()))?,
         elasticity_multiplier: MAINNET_ELASTICITY_MULTIPLIER,
         fee_configs: None,
     };
 
-    let input_bytes = rkyv::to_bytes::<rkyv::rancor::Error>(&input)
-        .map_err(|e| format!("Failed to serialize input to bytes: {}", e))?;
+    // The validation is a heuristic, so an input it flags is still written and
+    // handed to the prover rather than losing the block.
+    let validation_error = validate_program_input(&input).err();
+
+    let mut generated_input = write_input(&input, &output_dir, block_number)?;
+    generated_input.validation_error = validation_error;
+
+    Ok(generated_input)
+}
+
+/// Writes `input` as `{block_number}.bin` under `output_dir`, serializing it only
+/// once for the file, its digest and its stats.
+fn write_input(
+    input: &ProgramInput,
+    output_dir: &Path,
+    block_number: u64,
+) -> Result<GeneratedInput, InputGeneratorError> {
+    let input_bytes =
+        rkyv::to_bytes::<rkyv::rancor::Error>(input).map_err(InputGeneratorError::Serialization)?;
+
+    let input_path = output_dir.join(format!("{}.bin", block_number));
+
+    write_input_bytes(&input_bytes, &input_path).map_err(InputGeneratorError::Write)?;
+
+    Ok(GeneratedInput {
+        path: input_path,
+        digest: input_bytes_digest(&input_bytes),
+        stats: input_stats(input, input_bytes.len()),
+        validation_error: None,
+    })
+}
+
+/// What the `generate_input` NIF returns to Elixir.
+#[derive(rustler::NifMap)]
+struct GeneratedInputInfo {
+    path: String,
+    /// `0x`-prefixed hex of [`GeneratedInput::digest`].
+    digest: String,
+    stats: ProgramInputStats,
+    validation_error: Option<String>,
+}
+
+#[rustler::nif]
+fn generate_input(
+    rpc_block: String,
+    rpc_execution_witness: String,
+    output_dir: String,
+) -> Result<GeneratedInputInfo, String> {
+    let generated_input = generate_input_inner(
+        &rpc_block,
+        &rpc_execution_witness,
+        Some(Path::new(&output_dir)),
+    )
+    .map_err(|e| e.to_string())?;
+
+    let path = generated_input
+        .path
+        .to_str()
+        .ok_or("Failed to convert input path to str".to_string())?
+        .to_string();
+
+    Ok(GeneratedInputInfo {
+        path,
+        digest: format!("{:#x}", H256(generated_input.digest)),
+        stats: generated_input.stats,
+        validation_error: generated_input.validation_error.map(|e| e.to_string()),
+    })
+}
+
+#[rustler::nif]
+fn dump_input_json(input_path: String) -> Result<String, String> {
+    let input_path = Path::new(&input_path);
+
+    let input =
+        read_program_input(input_path).map_err(|e| format!("Failed to read input file: {}", e))?;
 
-    let input_path = Path::new(CARGO_MANIFEST_DIR)
-        .parent()
-        .ok_or("Failed to get parent directory".to_string())?
-        .parent()
-        .ok_or("Failed to get grandparent directory".to_string())?
-        .join(format!("{}.bin", block_number));
+    let input_json = program_input_to_json(&input)
+        .map_err(|e| format!("Failed to serialize input to JSON: {}", e))?;
 
-    std::fs::write(&input_path, input_bytes)
-        .map_err(|e| format!("Failed to write input file: {}", e))?;
+    let json_path = input_path.with_extension("json");
 
-    input_path
+    std::fs::write(&json_path, input_json)
+        .map_err(|e| format!("Failed to write input JSON file: {}", e))?;
+
+    json_path
         .to_str()
-        .ok_or("Failed to convert input path to str".to_string())
+        .ok_or("Failed to convert input JSON path to str".to_string())
         .map(|s| s.to_string())
 }
 
+#[rustler::nif]
+fn load_input(input_path: String) -> Result<bool, String> {
+    check_program_input_file(Path::new(&input_path)).map_err(|e| match e.kind() {
+        io::ErrorKind::InvalidData => format!("Input file is not a valid serialized input: {}", e),
+        _ => format!("Failed to read input file: {}", e),
+    })?;
+
+    Ok(true)
+}
+
 rustler::init!("Elixir.EthProofsClient.InputGenerator");
+
+#[cfg(test)]
+mod tests {
+    use ethrex_common::{
+        U256,
+        types::{Block, BlockBody, LegacyTransaction, Transaction, Withdrawal},
+    };
+    use ethrex_rlp::encode::RLPEncode;
+
+    use super::*;
+
+    fn fixture_input(block_number: u64) -> ProgramInput {
+        ProgramInput {
+            blocks: vec![Block::new(
+                BlockHeader {
+                    number: block_number,
+                    ..Default::default()
+                },
+                BlockBody::default(),
+            )],
+            ..Default::default()
+        }
+    }
+
+    fn rpc_block(number: u64, parent_hash: H256) -> RpcBlock {
+        let header = BlockHeader {
+            number,
+            parent_hash,
+            ..Default::default()
+        };
+        let hash = header.hash();
+
+        RpcBlock::build(header, BlockBody::default(), hash, true).unwrap()
+    }
+
+    fn rpc_execution_witness(headers: &[BlockHeader]) -> RpcExecutionWitness {
+        RpcExecutionWitness {
+            state: Vec::new(),
+            keys: Vec::new(),
+            codes: Vec::new(),
+            headers: headers
+                .iter()
+                .map(|header| header.encode_to_vec().into())
+                .collect(),
+        }
+    }
+
+    fn header(number: u64) -> BlockHeader {
+        BlockHeader {
+            number,
+            ..Default::default()
+        }
+    }
+
+    fn serialize(input: &ProgramInput) -> Vec<u8> {
+        rkyv::to_bytes::<rkyv::rancor::Error>(input)
+            .unwrap()
+            .to_vec()
+    }
+
+    #[test]
+    fn program_input_round_trips_through_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("100.bin");
+        let input = fixture_input(100);
+
+        write_program_input(&input, &path).unwrap();
+        let read_input = read_program_input(&path).unwrap();
+
+        assert_eq!(read_input.blocks[0].header.number, 100);
+        assert_eq!(serialize(&read_input), serialize(&input));
+    }
+
+    #[test]
+    fn execution_witness_with_block_parent_is_valid() {
+        let parent = header(99);
+        let block = rpc_block(100, parent.hash());
+
+        validate_execution_witness(&block, &rpc_execution_witness(&[header(98), parent])).unwrap();
+    }
+
+    #[test]
+    fn execution_witness_without_headers_is_rejected() {
+        let block = rpc_block(100, header(99).hash());
+
+        let err = validate_execution_witness(&block, &rpc_execution_witness(&[])).unwrap_err();
+
+        assert!(matches!(err, InputGeneratorError::EmptyExecutionWitness));
+    }
+
+    #[test]
+    fn execution_witness_for_another_block_is_rejected() {
+        let parent = header(98);
+        let block = rpc_block(100, parent.hash());
+
+        let err =
+            validate_execution_witness(&block, &rpc_execution_witness(&[parent])).unwrap_err();
+
+        assert!(matches!(
+            err,
+            InputGeneratorError::BlockNumberMismatch {
+                witness_parent: 98,
+                block: 100
+            }
+        ));
+    }
+
+    #[test]
+    fn execution_witness_with_other_parent_is_rejected() {
+        let block = rpc_block(100, H256::repeat_byte(0xab));
+
+        let err =
+            validate_execution_witness(&block, &rpc_execution_witness(&[header(99)])).unwrap_err();
+
+        assert!(matches!(
+            err,
+            InputGeneratorError::ParentHashMismatch { .. }
+        ));
+    }
+
+    #[test]
+    fn invalid_block_json_is_a_block_deserialization_error() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let err = generate_input_inner("not json", "{}", Some(dir.path())).unwrap_err();
+
+        assert!(matches!(
+            err,
+            InputGeneratorError::RpcBlockDeserialization(_)
+        ));
+    }
+
+    #[test]
+    fn invalid_witness_json_is_a_witness_deserialization_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let block = serde_json::to_string(&rpc_block(100, header(99).hash())).unwrap();
+
+        let err = generate_input_inner(&block, "not json", Some(dir.path())).unwrap_err();
+
+        assert!(matches!(
+            err,
+            InputGeneratorError::RpcExecutionWitnessDeserialization(_)
+        ));
+    }
+
+    #[test]
+    fn undecodable_witness_header_is_a_header_decoding_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let block = serde_json::to_string(&rpc_block(100, header(99).hash())).unwrap();
+        let mut witness = rpc_execution_witness(&[]);
+        witness.headers.push(vec![0x01].into());
+        let witness = serde_json::to_string(&witness).unwrap();
+
+        let err = generate_input_inner(&block, &witness, Some(dir.path())).unwrap_err();
+
+        assert!(matches!(
+            err,
+            InputGeneratorError::ExecutionWitnessHeaders(_)
+        ));
+    }
+
+    #[test]
+    fn failed_input_write_is_a_write_error() {
+        let dir = tempfile::tempdir().unwrap();
+
+        let err = write_input(&fixture_input(100), &dir.path().join("missing"), 100).unwrap_err();
+
+        assert!(matches!(err, InputGeneratorError::Write(_)));
+    }
+
+    fn tmp_files(dir: &Path) -> Vec<PathBuf> {
+        std::fs::read_dir(dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .filter(|path| path.extension().is_some_and(|extension| extension == "tmp"))
+            .collect()
+    }
+
+    #[test]
+    fn failed_write_to_missing_dir_leaves_no_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("missing").join("100.bin");
+
+        write_program_input(&fixture_input(100), &path).unwrap_err();
+
+        assert!(!path.exists());
+        assert!(tmp_files(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn failed_rename_removes_tmp_file() {
+        let dir = tempfile::tempdir().unwrap();
+        // A non-empty directory at the final path makes the rename fail after the
+        // temporary file has been written.
+        let path = dir.path().join("100.bin");
+        std::fs::create_dir(&path).unwrap();
+        std::fs::write(path.join("placeholder"), b"").unwrap();
+
+        write_program_input(&fixture_input(100), &path).unwrap_err();
+
+        assert!(path.is_dir());
+        assert!(tmp_files(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn identical_inputs_have_identical_digests() {
+        assert_eq!(
+            program_input_digest(&fixture_input(100)).unwrap(),
+            program_input_digest(&fixture_input(100)).unwrap()
+        );
+    }
+
+    #[test]
+    fn changing_the_block_changes_the_digest() {
+        assert_ne!(
+            program_input_digest(&fixture_input(100)).unwrap(),
+            program_input_digest(&fixture_input(101)).unwrap()
+        );
+    }
+
+    #[test]
+    fn written_input_digest_matches_program_input_digest() {
+        let dir = tempfile::tempdir().unwrap();
+        let input = fixture_input(100);
+
+        let generated_input = write_input(&input, dir.path(), 100).unwrap();
+
+        assert_eq!(generated_input.path, dir.path().join("100.bin"));
+        assert_eq!(
+            generated_input.digest,
+            program_input_digest(&input).unwrap()
+        );
+        assert_eq!(
+            generated_input.digest,
+            input_bytes_digest(&std::fs::read(&generated_input.path).unwrap())
+        );
+    }
+
+    #[test]
+    fn written_input_stats_count_blocks_and_witness_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut input = fixture_input(100);
+        input.execution_witness.keys = vec![
+            vec![0x01; 20],
+            vec![0x02; 20],
+            vec![0x03; 32],
+            vec![0x04; 32],
+            vec![0x05; 32],
+        ];
+
+        let generated_input = write_input(&input, dir.path(), 100).unwrap();
+
+        assert_eq!(
+            generated_input.stats,
+            ProgramInputStats {
+                serialized_bytes: std::fs::metadata(&generated_input.path).unwrap().len() as usize,
+                blocks: 1,
+                accounts: 2,
+                storage_slots: 3,
+            }
+        );
+        assert_eq!(program_input_stats(&input).unwrap(), generated_input.stats);
+    }
+
+    fn fixture_input_with_withdrawal(recipient: Address) -> ProgramInput {
+        let mut input = fixture_input(100);
+        let block = &mut input.blocks[0];
+        block.header.timestamp = 12;
+        block.header.parent_beacon_block_root = Some(H256::zero());
+        block.body.withdrawals = Some(vec![Withdrawal {
+            index: 0,
+            validator_index: 0,
+            address: recipient,
+            amount: 1,
+        }]);
+        input
+    }
+
+    #[test]
+    fn complete_witness_is_valid() {
+        let recipient = Address::repeat_byte(0x02);
+        let mut input = fixture_input_with_withdrawal(recipient);
+        input.execution_witness.keys = vec![
+            recipient.as_bytes().to_vec(),
+            BEACON_ROOTS_ADDRESS.as_bytes().to_vec(),
+            H256::from_low_u64_be(12).as_bytes().to_vec(),
+            H256::from_low_u64_be(12 + SYSTEM_CONTRACT_BUFFER_LENGTH)
+                .as_bytes()
+                .to_vec(),
+        ];
+
+        validate_program_input(&input).unwrap();
+    }
+
+    #[test]
+    fn incomplete_witness_reports_missing_keys() {
+        let recipient = Address::repeat_byte(0x02);
+        let mut input = fixture_input_with_withdrawal(recipient);
+        // Drops the withdrawal recipient and the second beacon roots slot.
+        input.execution_witness.keys = vec![
+            BEACON_ROOTS_ADDRESS.as_bytes().to_vec(),
+            H256::from_low_u64_be(12).as_bytes().to_vec(),
+        ];
+
+        match validate_program_input(&input).unwrap_err() {
+            InputValidationError::MissingKeys {
+                accounts,
+                storage_slots,
+            } => {
+                assert_eq!(accounts, vec![recipient]);
+                assert_eq!(
+                    storage_slots,
+                    vec![H256::from_low_u64_be(12 + SYSTEM_CONTRACT_BUFFER_LENGTH)]
+                );
+            }
+            err => panic!("unexpected error: {err}"),
+        }
+    }
+
+    /// Sender of the EIP-155 example transaction.
+    const EIP155_EXAMPLE_SENDER: Address = H160([
+        0x9d, 0x8a, 0x62, 0xf6, 0x56, 0xa8, 0xd1, 0x61, 0x5c, 0x12, 0x94, 0xfd, 0x71, 0xe9, 0xcf,
+        0xb3, 0xe4, 0x85, 0x5a, 0x4f,
+    ]);
+
+    /// The signed transaction from the EIP-155 example, with `r` and `s` as given.
+    fn eip155_example_transaction(r: U256, s: U256) -> Transaction {
+        Transaction::LegacyTransaction(LegacyTransaction {
+            nonce: 9,
+            gas_price: 20_000_000_000u64.into(),
+            gas: 21_000,
+            to: TxKind::Call(Address::repeat_byte(0x35)),
+            value: U256::exp10(18),
+            v: U256::from(37),
+            r,
+            s,
+            ..Default::default()
+        })
+    }
+
+    fn fixture_input_with_transaction(coinbase: Address) -> ProgramInput {
+        let mut input = fixture_input(100);
+        let block = &mut input.blocks[0];
+        block.header.coinbase = coinbase;
+        block.body.transactions = vec![eip155_example_transaction(
+            U256::from_dec_str(
+                "18515461264373351373200002665853028612451056578545711640558177340181847433846",
+            )
+            .unwrap(),
+            U256::from_dec_str(
+                "46948507304638947509940763649030358759909902576025900602547168820602576006531",
+            )
+            .unwrap(),
+        )];
+        input
+    }
+
+    #[test]
+    fn transaction_accounts_are_required() {
+        let coinbase = Address::repeat_byte(0x01);
+        let input = fixture_input_with_transaction(coinbase);
+
+        match validate_program_input(&input).unwrap_err() {
+            InputValidationError::MissingKeys {
+                accounts,
+                storage_slots,
+            } => {
+                assert_eq!(
+                    accounts,
+                    vec![coinbase, Address::repeat_byte(0x35), EIP155_EXAMPLE_SENDER]
+                );
+                assert!(storage_slots.is_empty());
+            }
+            err => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[test]
+    fn witness_with_transaction_accounts_is_valid() {
+        let coinbase = Address::repeat_byte(0x01);
+        let mut input = fixture_input_with_transaction(coinbase);
+        input.execution_witness.keys = vec![
+            coinbase.as_bytes().to_vec(),
+            Address::repeat_byte(0x35).as_bytes().to_vec(),
+            EIP155_EXAMPLE_SENDER.as_bytes().to_vec(),
+        ];
+
+        validate_program_input(&input).unwrap();
+    }
+
+    #[test]
+    fn unrecoverable_sender_is_a_sender_error() {
+        let tx = eip155_example_transaction(U256::zero(), U256::zero());
+        let mut input = fixture_input(100);
+        input.blocks[0].body.transactions = vec![tx.clone()];
+
+        match validate_program_input(&input).unwrap_err() {
+            InputValidationError::Sender { tx_hash, .. } => assert_eq!(tx_hash, tx.hash()),
+            err => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[test]
+    fn prague_block_requires_parent_hash_history_slot() {
+        let mut input = fixture_input(100);
+        input.blocks[0].header.requests_hash = Some(H256::zero());
+
+        match validate_program_input(&input).unwrap_err() {
+            InputValidationError::MissingKeys {
+                accounts,
+                storage_slots,
+            } => {
+                assert_eq!(accounts, vec![HISTORY_STORAGE_ADDRESS]);
+                assert_eq!(storage_slots, vec![H256::from_low_u64_be(99)]);
+            }
+            err => panic!("unexpected error: {err}"),
+        }
+    }
+
+    #[test]
+    fn empty_block_does_not_require_coinbase() {
+        let mut input = fixture_input(100);
+        input.blocks[0].header.coinbase = Address::repeat_byte(0x01);
+
+        validate_program_input(&input).unwrap();
+    }
+
+    #[test]
+    fn program_input_json_contains_the_block_number() {
+        let input_json = program_input_to_json(&fixture_input(12345)).unwrap();
+
+        let value: serde_json::Value = serde_json::from_str(&input_json).unwrap();
+        assert_eq!(
+            value["blocks"][0]["header"]["number"],
+            serde_json::json!("0x3039")
+        );
+    }
+
+    #[test]
+    fn written_input_file_is_valid() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("100.bin");
+        write_program_input(&fixture_input(100), &path).unwrap();
+
+        check_program_input_file(&path).unwrap();
+    }
+
+    #[test]
+    fn truncated_input_file_is_invalid_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("100.bin");
+        write_program_input(&fixture_input(100), &path).unwrap();
+        let input_bytes = std::fs::read(&path).unwrap();
+        std::fs::write(&path, &input_bytes[..input_bytes.len() / 2]).unwrap();
+
+        let err = check_program_input_file(&path).unwrap_err();
+
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn existing_output_dir_is_resolved_without_leftovers() {
+        let dir = tempfile::tempdir().unwrap();
+
+        assert_eq!(resolve_output_dir(Some(dir.path())).unwrap(), dir.path());
+        assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+
+    #[test]
+    fn missing_output_dir_is_not_found() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_dir = dir.path().join("missing");
+
+        let err = resolve_output_dir(Some(&output_dir)).unwrap_err();
+
+        assert!(matches!(err, InputGeneratorError::OutputDirNotFound(path) if path == output_dir));
+    }
+
+    #[test]
+    fn missing_output_dir_fails_before_generation() {
+        let dir = tempfile::tempdir().unwrap();
+
+        // The output directory is checked before the RPC data is even parsed.
+        let err = generate_input_inner("not json", "not json", Some(&dir.path().join("missing")))
+            .unwrap_err();
+
+        assert!(matches!(err, InputGeneratorError::OutputDirNotFound(_)));
+    }
+
+    #[test]
+    fn file_as_output_dir_is_not_a_directory() {
+        let dir = tempfile::tempdir().unwrap();
+        let output_dir = dir.path().join("file");
+        std::fs::write(&output_dir, b"").unwrap();
+
+        let err = resolve_output_dir(Some(&output_dir)).unwrap_err();
+
+        assert!(matches!(
+            err,
+            InputGeneratorError::OutputDirNotADirectory(_)
+        ));
+    }
+
+    #[test]
+    fn execution_witness_with_max_number_header_is_rejected() {
+        let parent = header(u64::MAX);
+        let block = rpc_block(100, parent.hash());
+
+        let err =
+            validate_execution_witness(&block, &rpc_execution_witness(&[parent])).unwrap_err();
+
+        assert!(matches!(
+            err,
+            InputGeneratorError::BlockNumberMismatch {
+                witness_parent: u64::MAX,
+                block: 100
+            }
+        ));
+    }
+
+    #[test]
+    fn block_with_only_transaction_hashes_is_a_block_conversion_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let parent = header(99);
+        let block_header = BlockHeader {
+            number: 100,
+            parent_hash: parent.hash(),
+            ..Default::default()
+        };
+        let hash = block_header.hash();
+        let body = BlockBody {
+            transactions: vec![Transaction::LegacyTransaction(LegacyTransaction::default())],
+            ..Default::default()
+        };
+        // Without full transactions the RPC block only carries their hashes, which
+        // can't be turned back into a block.
+        let block = RpcBlock::build(block_header, body, hash, false).unwrap();
+        let block = serde_json::to_string(&block).unwrap();
+        let witness = serde_json::to_string(&rpc_execution_witness(&[parent])).unwrap();
+
+        let err = generate_input_inner(&block, &witness, Some(dir.path())).unwrap_err();
+
+        assert!(matches!(err, InputGeneratorError::BlockConversion(_)));
+    }
+
+    #[test]
+    fn garbage_witness_state_is_an_execution_witness_error() {
+        let dir = tempfile::tempdir().unwrap();
+        let parent = header(99);
+        let block = serde_json::to_string(&rpc_block(100, parent.hash())).unwrap();
+        let mut witness = rpc_execution_witness(&[parent]);
+        witness.state.push(vec![0x01].into());
+        let witness = serde_json::to_string(&witness).unwrap();
+
+        let err = generate_input_inner(&block, &witness, Some(dir.path())).unwrap_err();
+
+        assert!(matches!(err, InputGeneratorError::ExecutionWitness(_)));
+    }
+}